@@ -0,0 +1,104 @@
+/// The stage of an ADSR envelope's state machine.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnvState {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// A per-sample ADSR envelope. `attack`/`decay`/`release` are in seconds,
+/// `sustain` is the held level in 0..1.
+#[derive(Clone, Copy, Debug)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    state: EnvState,
+    level: f32,
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Self {
+            attack: 0.01,
+            decay: 0.1,
+            sustain: 0.8,
+            release: 0.2,
+            state: EnvState::Idle,
+            level: 0.0,
+        }
+    }
+
+    pub fn note_on(&mut self) {
+        self.state = EnvState::Attack;
+    }
+
+    pub fn note_off(&mut self) {
+        if self.state != EnvState::Idle {
+            self.state = EnvState::Release;
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.state == EnvState::Idle
+    }
+
+    /// Advances the envelope by one sample and returns the new level.
+    pub fn advance(&mut self, sample_rate: f32) -> f32 {
+        match self.state {
+            EnvState::Idle => {}
+
+            EnvState::Attack => {
+                let step = if self.attack <= 0.0 {
+                    1.0
+                } else {
+                    1.0 / (self.attack * sample_rate)
+                };
+                self.level += step;
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.state = EnvState::Decay;
+                }
+            }
+
+            EnvState::Decay => {
+                let step = if self.decay <= 0.0 {
+                    self.level - self.sustain
+                } else {
+                    (1.0 - self.sustain) / (self.decay * sample_rate)
+                };
+                self.level -= step;
+                if self.level <= self.sustain {
+                    self.level = self.sustain;
+                    self.state = EnvState::Sustain;
+                }
+            }
+
+            EnvState::Sustain => {
+                self.level = self.sustain;
+            }
+
+            EnvState::Release => {
+                let step = if self.release <= 0.0 {
+                    self.level
+                } else {
+                    self.sustain / (self.release * sample_rate)
+                };
+                self.level -= step;
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.state = EnvState::Idle;
+                }
+            }
+        }
+
+        self.level
+    }
+}
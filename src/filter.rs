@@ -0,0 +1,80 @@
+/// An RBJ-cookbook resonant low-pass biquad, run in Direct Form I. Call
+/// `set_cutoff`/`set_resonance` only when their knobs change; `process` is
+/// just the difference equation and does no trigonometry per sample.
+#[derive(Clone, Copy, Debug)]
+pub struct LowPassFilter {
+    cutoff: f32,
+    resonance: f32,
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl LowPassFilter {
+    pub fn new(cutoff: f32, resonance: f32, sample_rate: f32) -> Self {
+        let mut filter = Self {
+            cutoff,
+            resonance,
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        };
+        filter.recompute(sample_rate);
+        filter
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32, sample_rate: f32) {
+        self.cutoff = cutoff;
+        self.recompute(sample_rate);
+    }
+
+    pub fn set_resonance(&mut self, resonance: f32, sample_rate: f32) {
+        self.resonance = resonance;
+        self.recompute(sample_rate);
+    }
+
+    fn recompute(&mut self, sample_rate: f32) {
+        let w0 = 2.0 * std::f32::consts::PI * self.cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * self.resonance);
+        let cosw = w0.cos();
+
+        let b0 = (1.0 - cosw) / 2.0;
+        let b1 = 1.0 - cosw;
+        let b2 = (1.0 - cosw) / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cosw;
+        let a2 = 1.0 - alpha;
+
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+
+    /// Filters one sample, updating the Direct Form I state.
+    pub fn process(&mut self, x0: f32) -> f32 {
+        let y0 = self.b0 * x0 + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+
+        self.x2 = self.x1;
+        self.x1 = x0;
+        self.y2 = self.y1;
+        self.y1 = y0;
+
+        y0
+    }
+}
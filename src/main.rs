@@ -1,6 +1,33 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use vizia::prelude::*;
 
+mod envelope;
+mod filter;
+mod midi;
+mod oscilloscope;
+mod recorder;
+mod voice;
+mod waveform;
+
+use filter::LowPassFilter;
+use oscilloscope::Oscilloscope;
+use recorder::RecorderCommand;
+use voice::VoicePool;
+use waveform::Waveform;
+
+// Only every Nth output sample is kept for the oscilloscope, so a block
+// covers a wider time window without shipping full-resolution audio to the
+// GUI thread.
+const SCOPE_DECIMATION: usize = 16;
+
+// How many decimated samples the audio thread batches into one oscilloscope
+// update.
+const SCOPE_BLOCK_LEN: usize = 256;
+
+// A note identity reserved for the GUI's virtual `KeyZ` key, distinct from
+// any real MIDI note number (0..127).
+const KEYZ_NOTE_ID: u32 = u32::MAX;
+
 static THEME: &'static str = include_str!("theme.css");
 
 // Messages to pass between gui and audio threads
@@ -8,7 +35,16 @@ static THEME: &'static str = include_str!("theme.css");
 pub enum Message {
     Frequency(f32),
     Amplitude(f32),
-    Note(f32),
+    NoteOn { note: u32, freq: f32, velocity: f32 },
+    NoteOff { note: u32 },
+    Waveform(Waveform),
+    Attack(f32),
+    Decay(f32),
+    Sustain(f32),
+    Release(f32),
+    Cutoff(f32),
+    Resonance(f32),
+    Record(bool),
 }
 
 // A controller widget which holds the knobs and the message channel
@@ -17,11 +53,52 @@ struct AppData {
     command_sender: crossbeam_channel::Sender<Message>,
     amplitude: f32,
     frequency: f32,
+    midi_port_name: String,
+    waveforms: Vec<String>,
+    waveform_index: usize,
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+    cutoff: f32,
+    resonance: f32,
+    recording: bool,
+    waveform_buffer: Vec<f32>,
 }
 
 pub enum AppEvent {
     SetAmplitude(f32),
     SetFrequency(f32),
+    SetWaveform(usize),
+    SetAttack(f32),
+    SetDecay(f32),
+    SetSustain(f32),
+    SetRelease(f32),
+    SetCutoff(f32),
+    SetResonance(f32),
+    ToggleRecord,
+    PushSamples(Vec<f32>),
+}
+
+// Maps the normalized (0..1) frequency knob value to the 440-2000 Hz range
+// the oscillator actually plays.
+fn knob_to_frequency(val: f32) -> f32 {
+    440.0 + val * (2000.0 - 440.0)
+}
+
+// Maps a normalized (0..1) knob value to an envelope time in seconds.
+fn knob_to_time(val: f32) -> f32 {
+    val * 2.0
+}
+
+// Maps a normalized (0..1) knob value to a filter cutoff in Hz.
+fn knob_to_cutoff(val: f32) -> f32 {
+    200.0 + val * (8000.0 - 200.0)
+}
+
+// Maps a normalized (0..1) knob value to a filter Q (resonance).
+fn knob_to_resonance(val: f32) -> f32 {
+    0.5 + val * (10.0 - 0.5)
 }
 
 impl Model for AppData {
@@ -40,15 +117,86 @@ impl Model for AppData {
                     .send(Message::Frequency(self.frequency))
                     .unwrap();
             }
+
+            AppEvent::SetWaveform(index) => {
+                self.waveform_index = *index;
+                self.command_sender
+                    .send(Message::Waveform(Waveform::ALL[self.waveform_index]))
+                    .unwrap();
+            }
+
+            AppEvent::SetAttack(val) => {
+                self.attack = *val;
+                self.command_sender
+                    .send(Message::Attack(knob_to_time(self.attack)))
+                    .unwrap();
+            }
+
+            AppEvent::SetDecay(val) => {
+                self.decay = *val;
+                self.command_sender
+                    .send(Message::Decay(knob_to_time(self.decay)))
+                    .unwrap();
+            }
+
+            AppEvent::SetSustain(val) => {
+                self.sustain = *val;
+                self.command_sender
+                    .send(Message::Sustain(self.sustain))
+                    .unwrap();
+            }
+
+            AppEvent::SetRelease(val) => {
+                self.release = *val;
+                self.command_sender
+                    .send(Message::Release(knob_to_time(self.release)))
+                    .unwrap();
+            }
+
+            AppEvent::SetCutoff(val) => {
+                self.cutoff = *val;
+                self.command_sender
+                    .send(Message::Cutoff(knob_to_cutoff(self.cutoff)))
+                    .unwrap();
+            }
+
+            AppEvent::SetResonance(val) => {
+                self.resonance = *val;
+                self.command_sender
+                    .send(Message::Resonance(knob_to_resonance(self.resonance)))
+                    .unwrap();
+            }
+
+            AppEvent::ToggleRecord => {
+                self.recording = !self.recording;
+                self.command_sender
+                    .send(Message::Record(self.recording))
+                    .unwrap();
+            }
+
+            AppEvent::PushSamples(samples) => {
+                self.waveform_buffer = samples.clone();
+            }
         });
 
         event.map(|window_event, _| match window_event {
             WindowEvent::KeyDown(code, _) if *code == Code::KeyZ => {
-                self.command_sender.send(Message::Note(1.0)).unwrap();
+                let freq = knob_to_frequency(self.frequency);
+                self.command_sender
+                    .send(Message::NoteOn {
+                        note: KEYZ_NOTE_ID,
+                        freq,
+                        velocity: 1.0,
+                    })
+                    .unwrap();
             }
 
             WindowEvent::KeyUp(code, _) if *code == Code::KeyZ => {
-                self.command_sender.send(Message::Note(0.0)).unwrap();
+                self.command_sender
+                    .send(Message::NoteOff {
+                        note: KEYZ_NOTE_ID,
+                    })
+                    .unwrap();
             }
 
             _ => {}
@@ -57,12 +205,23 @@ impl Model for AppData {
 }
 
 impl AppData {
-    pub fn new(command_sender: crossbeam_channel::Sender<Message>) -> Self {
+    pub fn new(command_sender: crossbeam_channel::Sender<Message>, midi_port_name: String) -> Self {
         Self {
             command_sender,
 
             amplitude: 0.1,
             frequency: 0.0,
+            midi_port_name,
+            waveforms: Waveform::ALL.iter().map(|w| w.to_string()).collect(),
+            waveform_index: 0,
+            attack: 0.0,
+            decay: 0.1,
+            sustain: 0.8,
+            release: 0.1,
+            cutoff: 1.0,
+            resonance: 0.03,
+            recording: false,
+            waveform_buffer: vec![0.0; SCOPE_BLOCK_LEN],
         }
     }
 }
@@ -71,6 +230,20 @@ fn main() {
     // Create a channel for sending messages between threads
     let (command_sender, command_receiver) = crossbeam_channel::bounded(1024);
 
+    // Open the default MIDI input port, if any, and forward note events onto
+    // the same channel the GUI uses.
+    let midi_port_name =
+        midi::connect(command_sender.clone()).unwrap_or_else(|| "No MIDI input".to_string());
+
+    // A second, audio-to-GUI channel carrying downsampled output blocks for
+    // the oscilloscope. Bounded and non-blocking on the send side, so the
+    // audio thread drops a block rather than ever waiting on the GUI.
+    let (scope_sender, scope_receiver) = crossbeam_channel::bounded(4);
+
+    // The recorder thread owns the WAV writer and does all the blocking file
+    // I/O, so the audio thread only ever pushes frames onto this channel.
+    let recorder_sender = recorder::spawn();
+
     // Move audio playback into another thread
     std::thread::spawn(move || {
         let host = cpal::default_host();
@@ -83,15 +256,36 @@ fn main() {
 
         match config.sample_format() {
             cpal::SampleFormat::F32 => {
-                run::<f32>(&device, &config.into(), command_receiver.clone()).unwrap();
+                run::<f32>(
+                    &device,
+                    &config.into(),
+                    command_receiver.clone(),
+                    scope_sender.clone(),
+                    recorder_sender.clone(),
+                )
+                .unwrap();
             }
 
             cpal::SampleFormat::I16 => {
-                run::<i16>(&device, &config.into(), command_receiver.clone()).unwrap();
+                run::<i16>(
+                    &device,
+                    &config.into(),
+                    command_receiver.clone(),
+                    scope_sender.clone(),
+                    recorder_sender.clone(),
+                )
+                .unwrap();
             }
 
             cpal::SampleFormat::U16 => {
-                run::<u16>(&device, &config.into(), command_receiver.clone()).unwrap();
+                run::<u16>(
+                    &device,
+                    &config.into(),
+                    command_receiver.clone(),
+                    scope_sender.clone(),
+                    recorder_sender.clone(),
+                )
+                .unwrap();
             }
         }
     });
@@ -99,7 +293,15 @@ fn main() {
     Application::new(move |cx| {
         cx.add_theme(THEME);
 
-        AppData::new(command_sender.clone()).build(cx);
+        AppData::new(command_sender.clone(), midi_port_name.clone()).build(cx);
+
+        // Drain the scope channel on a background task and feed each block
+        // into the model as it arrives.
+        cx.spawn(move |cx| {
+            while let Ok(samples) = scope_receiver.recv() {
+                let _ = cx.emit(AppEvent::PushSamples(samples));
+            }
+        });
 
         HStack::new(cx, |cx| {
             VStack::new(cx, |cx| {
@@ -109,6 +311,17 @@ fn main() {
             })
             .class("control");
 
+            VStack::new(cx, |cx| {
+                Label::new(cx, AppData::midi_port_name);
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                PickList::new(cx, AppData::waveforms, AppData::waveform_index, true)
+                    .on_select(|cx, index| cx.emit(AppEvent::SetWaveform(index)));
+            })
+            .class("control");
+
             VStack::new(cx, |cx| {
                 Knob::new(cx, 0.0, AppData::frequency, false)
                     .on_changing(|cx, val| cx.emit(AppEvent::SetFrequency(val)));
@@ -119,11 +332,73 @@ fn main() {
                 );
             })
             .class("control");
+
+            VStack::new(cx, |cx| {
+                Knob::new(cx, 0.0, AppData::attack, false)
+                    .on_changing(|cx, val| cx.emit(AppEvent::SetAttack(val)));
+                Label::new(cx, "Attack");
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                Knob::new(cx, 0.1, AppData::decay, false)
+                    .on_changing(|cx, val| cx.emit(AppEvent::SetDecay(val)));
+                Label::new(cx, "Decay");
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                Knob::new(cx, 0.8, AppData::sustain, false)
+                    .on_changing(|cx, val| cx.emit(AppEvent::SetSustain(val)));
+                Label::new(cx, "Sustain");
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                Knob::new(cx, 0.1, AppData::release, false)
+                    .on_changing(|cx, val| cx.emit(AppEvent::SetRelease(val)));
+                Label::new(cx, "Release");
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                Knob::new(cx, 1.0, AppData::cutoff, false)
+                    .on_changing(|cx, val| cx.emit(AppEvent::SetCutoff(val)));
+                Label::new(cx, "Cutoff");
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                Knob::new(cx, 0.03, AppData::resonance, false)
+                    .on_changing(|cx, val| cx.emit(AppEvent::SetResonance(val)));
+                Label::new(cx, "Resonance");
+            })
+            .class("control");
+
+            VStack::new(cx, |cx| {
+                Button::new(
+                    cx,
+                    |cx| cx.emit(AppEvent::ToggleRecord),
+                    |cx| {
+                        Label::new(
+                            cx,
+                            AppData::recording
+                                .map(|recording| if *recording { "Stop" } else { "Record" }.to_string()),
+                        )
+                    },
+                );
+            })
+            .class("control");
+
+            Oscilloscope::new(cx, AppData::waveform_buffer)
+                .class("scope")
+                .width(Pixels(300.0))
+                .height(Pixels(160.0));
         })
         .class("content");
     })
     .title("Audio Synth")
-    .inner_size((200, 120))
+    .inner_size((1400, 220))
     .run();
 }
 
@@ -131,6 +406,8 @@ fn run<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
     command_receiver: crossbeam_channel::Receiver<Message>,
+    scope_sender: crossbeam_channel::Sender<Vec<f32>>,
+    recorder_sender: crossbeam_channel::Sender<RecorderCommand>,
 ) -> Result<(), anyhow::Error>
 where
     T: cpal::Sample,
@@ -142,10 +419,18 @@ where
     let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
 
     // Define some variables we need for a simple oscillator
-    let mut phi = 0.0f32;
-    let mut frequency = 440.0f32;
     let mut amplitude = 0.1;
-    let mut note = 0.0;
+    let mut waveform = Waveform::Sine;
+    let mut voice_pool = VoicePool::new(
+        knob_to_time(0.0),
+        knob_to_time(0.1),
+        0.8,
+        knob_to_time(0.1),
+    );
+    let mut filter = LowPassFilter::new(knob_to_cutoff(1.0), knob_to_resonance(0.03), sample_rate);
+    let mut recording = false;
+    let mut scope_buffer = Vec::with_capacity(SCOPE_BLOCK_LEN);
+    let mut scope_decimation_count = 0usize;
 
     // Build an output stream
     let stream = device.build_output_stream(
@@ -156,29 +441,91 @@ where
                 // Try to receive a message from the gui thread
                 while let Ok(command) = command_receiver.try_recv() {
                     match command {
-                        Message::Note(val) => {
-                            note = val;
+                        Message::NoteOn { note, freq, velocity } => {
+                            voice_pool.note_on(note, freq, velocity);
+                        }
+
+                        Message::NoteOff { note } => {
+                            voice_pool.note_off(note);
                         }
 
                         Message::Amplitude(val) => {
                             amplitude = val;
                         }
 
-                        Message::Frequency(val) => {
-                            frequency = (val * (2000.0 - 440.0)) + 440.0;
+                        Message::Frequency(_) => {
+                            // Kept for the GUI's frequency knob display; each
+                            // voice now tracks its own pitch once triggered.
+                        }
+
+                        Message::Waveform(val) => {
+                            waveform = val;
+                        }
+
+                        Message::Attack(val) => {
+                            voice_pool.set_attack(val);
+                        }
+
+                        Message::Decay(val) => {
+                            voice_pool.set_decay(val);
+                        }
+
+                        Message::Sustain(val) => {
+                            voice_pool.set_sustain(val);
+                        }
+
+                        Message::Release(val) => {
+                            voice_pool.set_release(val);
+                        }
+
+                        Message::Cutoff(val) => {
+                            filter.set_cutoff(val, sample_rate);
+                        }
+
+                        Message::Resonance(val) => {
+                            filter.set_resonance(val, sample_rate);
+                        }
+
+                        Message::Record(new_recording) => {
+                            recording = new_recording;
+                            let _ = recorder_sender.send(if recording {
+                                RecorderCommand::Start {
+                                    sample_rate,
+                                    channels: channels as u16,
+                                }
+                            } else {
+                                RecorderCommand::Stop
+                            });
                         }
                     }
                 }
 
-                // This creates a 'phase clock' which varies between 0.0 and 1.0 with a rate of frequency
-                phi = (phi + (frequency / sample_rate)).fract();
+                // Sum every sounding voice, scaled down so chords don't clip
+                let mix = amplitude * voice_pool.advance(sample_rate, waveform);
 
-                // Generate a sine wave signal
-                let make_noise =
-                    |phi: f32| -> f32 { amplitude * note * (2.0f32 * 3.141592f32 * phi).sin() };
+                // Shape the mix with the resonant low-pass filter
+                let mix = filter.process(mix);
+
+                if recording {
+                    let _ = recorder_sender.send(RecorderCommand::Frame(mix));
+                }
+
+                // Decimate, batch, and ship samples off to the GUI once a
+                // block is full, dropping it if the scope channel is already
+                // full because the GUI thread has fallen behind.
+                scope_decimation_count += 1;
+                if scope_decimation_count >= SCOPE_DECIMATION {
+                    scope_decimation_count = 0;
+
+                    scope_buffer.push(mix);
+                    if scope_buffer.len() >= SCOPE_BLOCK_LEN {
+                        let _ = scope_sender.try_send(scope_buffer.clone());
+                        scope_buffer.clear();
+                    }
+                }
 
-                // Convert the make_noise output into a sample
-                let value: T = cpal::Sample::from::<f32>(&make_noise(phi));
+                // Convert the filtered mix into a sample
+                let value: T = cpal::Sample::from::<f32>(&mix);
 
                 // Assign this sample to all channels in the frame
                 for sample in frame.iter_mut() {
@@ -0,0 +1,57 @@
+use crate::Message;
+
+/// Opens the default MIDI input port and forwards note-on/note-off events to
+/// the audio thread over `sender`. Returns the connected port's name so the
+/// GUI can display it, or `None` if no MIDI input is available.
+pub fn connect(sender: crossbeam_channel::Sender<Message>) -> Option<String> {
+    let mut midi_in = midir::MidiInput::new("vizia-audio-synth").ok()?;
+    midi_in.ignore(midir::Ignore::None);
+
+    let port = midi_in.ports().into_iter().next()?;
+    let port_name = midi_in.port_name(&port).ok()?;
+
+    let connection = midi_in
+        .connect(
+            &port,
+            "vizia-audio-synth-input",
+            move |_stamp, message, _| {
+                if let Some(event) = parse_note_event(message) {
+                    let _ = sender.send(event);
+                }
+            },
+            (),
+        )
+        .ok()?;
+
+    // The callback above must keep running for the lifetime of the program,
+    // so leak the connection rather than tying it to a value we'd have to
+    // thread back out of here.
+    std::mem::forget(connection);
+
+    Some(port_name)
+}
+
+// Parses a raw MIDI message into a note-on/note-off `Message`, handling the
+// convention that a note-on with velocity 0 means note-off.
+fn parse_note_event(message: &[u8]) -> Option<Message> {
+    let (status, note, velocity) = match message {
+        [status, note, velocity] => (*status, *note, *velocity),
+        _ => return None,
+    };
+
+    let freq = note_to_freq(note);
+
+    match status & 0xF0 {
+        0x90 if velocity > 0 => Some(Message::NoteOn {
+            note: note as u32,
+            freq,
+            velocity: velocity as f32 / 127.0,
+        }),
+        0x90 | 0x80 => Some(Message::NoteOff { note: note as u32 }),
+        _ => None,
+    }
+}
+
+fn note_to_freq(note: u8) -> f32 {
+    440.0 * 2.0f32.powf((note as f32 - 69.0) / 12.0)
+}
@@ -0,0 +1,54 @@
+use vizia::prelude::*;
+use vizia::vg;
+
+/// Plots a rolling buffer of audio samples as a line, giving visual
+/// feedback of amplitude, waveform shape and envelope.
+pub struct Oscilloscope<L> {
+    samples: L,
+}
+
+impl<L> Oscilloscope<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    pub fn new(cx: &mut Context, samples: L) -> Handle<Self> {
+        Self { samples }.build(cx, |_| {})
+    }
+}
+
+impl<L> View for Oscilloscope<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("oscilloscope")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let samples = self.samples.get(cx);
+
+        if samples.is_empty() {
+            return;
+        }
+
+        let mut path = vg::Path::new();
+        let mut paint = vg::Paint::color(vg::Color::rgb(80, 220, 120));
+        paint.set_line_width(1.5);
+
+        let step = bounds.w / (samples.len().max(1) as f32);
+
+        for (i, sample) in samples.iter().enumerate() {
+            let x = bounds.x + i as f32 * step;
+            let y = bounds.y + bounds.h / 2.0 - sample.clamp(-1.0, 1.0) * (bounds.h / 2.0);
+
+            if i == 0 {
+                path.move_to(x, y);
+            } else {
+                path.line_to(x, y);
+            }
+        }
+
+        canvas.stroke_path(&mut path, &paint);
+    }
+}
@@ -0,0 +1,83 @@
+use crossbeam_channel::{Receiver, Sender};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::BufWriter;
+
+/// Commands sent from the audio thread to the recorder thread. Opening and
+/// finalizing the WAV file are both blocking syscalls, so the audio thread
+/// only ever pushes these onto the channel and never touches the file
+/// itself.
+pub enum RecorderCommand {
+    Start { sample_rate: f32, channels: u16 },
+    Stop,
+    Frame(f32),
+}
+
+/// Spawns the thread that owns the WAV writer, returning a sender the audio
+/// thread can push to. The channel is unbounded so a send can never block
+/// the audio callback the way a full bounded channel (as used for the
+/// oscilloscope) would; unlike scope blocks, a dropped recording frame would
+/// be an audible gap, not just a stale display.
+pub fn spawn() -> Sender<RecorderCommand> {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || run(receiver));
+    sender
+}
+
+fn run(receiver: Receiver<RecorderCommand>) {
+    let mut writer: Option<WavWriter<BufWriter<File>>> = None;
+    let mut channels: u16 = 0;
+
+    for command in receiver {
+        match command {
+            RecorderCommand::Start {
+                sample_rate,
+                channels: new_channels,
+            } => {
+                if writer.is_some() {
+                    continue;
+                }
+
+                channels = new_channels;
+
+                let spec = WavSpec {
+                    channels,
+                    sample_rate: sample_rate as u32,
+                    bits_per_sample: 32,
+                    sample_format: SampleFormat::Float,
+                };
+
+                let path = format!(
+                    "recording_{}.wav",
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|duration| duration.as_secs())
+                        .unwrap_or(0)
+                );
+
+                match WavWriter::create(&path, spec) {
+                    Ok(new_writer) => writer = Some(new_writer),
+                    Err(err) => eprintln!("failed to start recording: {}", err),
+                }
+            }
+
+            RecorderCommand::Stop => {
+                if let Some(writer) = writer.take() {
+                    if let Err(err) = writer.finalize() {
+                        eprintln!("failed to finalize recording: {}", err);
+                    }
+                }
+            }
+
+            RecorderCommand::Frame(sample) => {
+                if let Some(writer) = writer.as_mut() {
+                    for _ in 0..channels {
+                        if let Err(err) = writer.write_sample(sample) {
+                            eprintln!("failed to write sample: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::envelope::Envelope;
+use crate::waveform::Waveform;
+
+const NUM_VOICES: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+struct Voice {
+    note_id: Option<u32>,
+    freq: f32,
+    velocity: f32,
+    phi: f32,
+    envelope: Envelope,
+}
+
+impl Voice {
+    fn new() -> Self {
+        Self {
+            note_id: None,
+            freq: 0.0,
+            velocity: 0.0,
+            phi: 0.0,
+            envelope: Envelope::new(),
+        }
+    }
+}
+
+/// A fixed pool of voices so chords held from MIDI or the GUI can sound
+/// together instead of the oscillator being limited to one pitch. All
+/// voices share the same ADSR settings, set via `set_attack`/`set_decay`/
+/// `set_sustain`/`set_release`.
+pub struct VoicePool {
+    voices: [Voice; NUM_VOICES],
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+}
+
+impl VoicePool {
+    /// `attack`/`decay`/`sustain`/`release` should already be converted from
+    /// their knob values, matching the engine's initial state to what the
+    /// GUI shows at startup.
+    pub fn new(attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self {
+            voices: [Voice::new(); NUM_VOICES],
+            attack,
+            decay,
+            sustain,
+            release,
+        }
+    }
+
+    pub fn set_attack(&mut self, val: f32) {
+        self.attack = val;
+    }
+
+    pub fn set_decay(&mut self, val: f32) {
+        self.decay = val;
+    }
+
+    pub fn set_sustain(&mut self, val: f32) {
+        self.sustain = val;
+    }
+
+    pub fn set_release(&mut self, val: f32) {
+        self.release = val;
+    }
+
+    /// Assigns `freq`/`velocity` to the voice for `note_id`, retriggering it
+    /// if that note is already sounding (held or releasing), otherwise a
+    /// free voice or, failing that, the quietest one stolen from another
+    /// note.
+    pub fn note_on(&mut self, note_id: u32, freq: f32, velocity: f32) {
+        let index = self
+            .voices
+            .iter()
+            .position(|voice| voice.note_id == Some(note_id))
+            .or_else(|| self.voices.iter().position(|voice| voice.envelope.is_idle()))
+            .unwrap_or_else(|| {
+                self.voices
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        a.envelope.level().partial_cmp(&b.envelope.level()).unwrap()
+                    })
+                    .map(|(index, _)| index)
+                    .unwrap()
+            });
+
+        let voice = &mut self.voices[index];
+        voice.note_id = Some(note_id);
+        voice.freq = freq;
+        voice.velocity = velocity;
+        voice.phi = 0.0;
+        voice.envelope = Envelope::new();
+        voice.envelope.attack = self.attack;
+        voice.envelope.decay = self.decay;
+        voice.envelope.sustain = self.sustain;
+        voice.envelope.release = self.release;
+        voice.envelope.note_on();
+    }
+
+    /// Releases the voice currently holding `note_id`, if any. Tracking the
+    /// note's identity (rather than just its frequency) means retriggering
+    /// the same note while its previous voice is still releasing can't
+    /// release the wrong voice.
+    pub fn note_off(&mut self, note_id: u32) {
+        if let Some(voice) = self
+            .voices
+            .iter_mut()
+            .find(|voice| voice.note_id == Some(note_id) && !voice.envelope.is_idle())
+        {
+            voice.envelope.note_off();
+        }
+    }
+
+    /// Advances every non-idle voice by one sample, returning the mixed
+    /// output scaled so chords don't clip as more voices join in.
+    pub fn advance(&mut self, sample_rate: f32, waveform: Waveform) -> f32 {
+        let mut sum = 0.0;
+        let mut active_voices = 0;
+
+        for voice in self.voices.iter_mut() {
+            if voice.envelope.is_idle() {
+                continue;
+            }
+
+            let dt = voice.freq / sample_rate;
+            voice.phi = (voice.phi + dt).fract();
+            let level = voice.envelope.advance(sample_rate);
+
+            sum += voice.velocity * level * waveform.evaluate(voice.phi, dt);
+            active_voices += 1;
+        }
+
+        sum / (active_voices.max(1) as f32)
+    }
+}
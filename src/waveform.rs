@@ -0,0 +1,115 @@
+/// The shape of the oscillator's output waveform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Saw,
+    Square,
+    Triangle,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [
+        Waveform::Sine,
+        Waveform::Saw,
+        Waveform::Square,
+        Waveform::Triangle,
+    ];
+
+    /// Evaluates the waveform at phase `phi` (0..1), band-limiting the
+    /// discontinuous shapes with PolyBLEP given the phase increment per
+    /// sample `dt = frequency / sample_rate`.
+    pub fn evaluate(self, phi: f32, dt: f32) -> f32 {
+        match self {
+            Waveform::Sine => (2.0 * std::f32::consts::PI * phi).sin(),
+
+            Waveform::Saw => (2.0 * phi - 1.0) - poly_blep(phi, dt),
+
+            Waveform::Square => {
+                let naive = if phi < 0.5 { 1.0 } else { -1.0 };
+                naive + poly_blep(phi, dt) - poly_blep((phi + 0.5).fract(), dt)
+            }
+
+            Waveform::Triangle => 4.0 * (phi - 0.5).abs() - 1.0,
+        }
+    }
+}
+
+impl std::fmt::Display for Waveform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Waveform::Sine => "Sine",
+            Waveform::Saw => "Saw",
+            Waveform::Square => "Square",
+            Waveform::Triangle => "Triangle",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// PolyBLEP (polynomial band-limited step) correction for the discontinuity
+// at phase wrap (phi == 0), smoothing the naive waveform over one sample
+// either side of the edge to suppress aliasing. The residual is -1 just after
+// the edge and +1 just before it, so it is subtracted where the naive waveform
+// steps down and added where it steps up.
+fn poly_blep(phi: f32, dt: f32) -> f32 {
+    if phi < dt {
+        let t = phi / dt;
+        t + t - t * t - 1.0
+    } else if phi > 1.0 - dt {
+        let t = (phi - 1.0) / dt;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poly_blep_is_zero_away_from_the_edges() {
+        assert_eq!(poly_blep(0.5, 0.01), 0.0);
+    }
+
+    #[test]
+    fn poly_blep_mirrors_across_the_wrap_point() {
+        // phi == 0 and phi == 1 are the same instant in the cycle; the
+        // correction cancels a step there, so it is -1 on one side and +1
+        // on the other.
+        let dt = 0.01;
+        assert!((poly_blep(0.0, dt) + poly_blep(1.0 - f32::EPSILON, dt)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn saw_is_continuous_across_the_wrap() {
+        let dt = 0.01;
+        let just_before = Waveform::Saw.evaluate(1.0 - dt, dt);
+        let just_after = Waveform::Saw.evaluate(0.0, dt);
+        assert!((just_before - just_after).abs() < 0.25);
+    }
+
+    #[test]
+    fn square_is_continuous_across_its_edges() {
+        let dt = 0.01;
+        let before_wrap = Waveform::Square.evaluate(1.0 - dt, dt);
+        let after_wrap = Waveform::Square.evaluate(0.0, dt);
+        assert!((before_wrap - after_wrap).abs() < 0.25);
+
+        let before_half = Waveform::Square.evaluate(0.5 - dt, dt);
+        let after_half = Waveform::Square.evaluate(0.5, dt);
+        assert!((before_half - after_half).abs() < 0.25);
+    }
+
+    #[test]
+    fn sine_matches_known_values() {
+        assert!((Waveform::Sine.evaluate(0.0, 0.0) - 0.0).abs() < 1e-6);
+        assert!((Waveform::Sine.evaluate(0.25, 0.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn triangle_matches_known_values() {
+        assert!((Waveform::Triangle.evaluate(0.0, 0.0) - 1.0).abs() < 1e-6);
+        assert!((Waveform::Triangle.evaluate(0.5, 0.0) - (-1.0)).abs() < 1e-6);
+    }
+}